@@ -1,5 +1,6 @@
 use anyhow::{self, format_err};
 use app_dirs::{AppDataType, AppInfo};
+use chrono::{DateTime, Utc};
 use directories;
 use duct::cmd;
 use pathdiff::diff_paths;
@@ -9,8 +10,11 @@ use structopt::StructOpt;
 use toml;
 use whoami;
 
+use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{Read, Write};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
 
 const APP_INFO: AppInfo = AppInfo {
     name: env!("CARGO_PKG_NAME"),
@@ -20,25 +24,174 @@ const APP_INFO: AppInfo = AppInfo {
 #[derive(Debug, Deserialize, Serialize)]
 struct Backup {
     password: String,
+    #[serde(default)]
+    password_command: Option<String>,
+    #[serde(default)]
+    password_file: Option<PathBuf>,
     excludes: Vec<String>,
     targets: Vec<String>,
-    repository: Repository,
+    #[serde(default)]
+    retention: Option<Retention>,
+}
+
+impl Backup {
+    /// The `RESTIC_PASSWORD*` env var restic should use to unlock the
+    /// repository, preferring `password_command`/`password_file` so the
+    /// real key can live in `pass`, a keyring, or an age-encrypted file
+    /// instead of in plaintext in `config.toml`.
+    fn password_env(&self) -> (&'static str, String) {
+        if let Some(command) = &self.password_command {
+            return ("RESTIC_PASSWORD_COMMAND", command.clone());
+        }
+        if let Some(file) = &self.password_file {
+            return ("RESTIC_PASSWORD_FILE", file.display().to_string());
+        }
+        ("RESTIC_PASSWORD", self.password.clone())
+    }
+}
+
+#[test]
+fn test_backup_password_env_precedence() {
+    let backup = |password_command, password_file| Backup {
+        password: "literal".to_string(),
+        password_command,
+        password_file,
+        excludes: vec![],
+        targets: vec![],
+        retention: None,
+    };
+
+    assert_eq!(
+        backup(None, None).password_env(),
+        ("RESTIC_PASSWORD", "literal".to_string())
+    );
+    assert_eq!(
+        backup(None, Some(PathBuf::from("/run/secrets/restic"))).password_env(),
+        ("RESTIC_PASSWORD_FILE", "/run/secrets/restic".to_string())
+    );
+    assert_eq!(
+        backup(
+            Some("pass show restic".to_string()),
+            Some(PathBuf::from("/run/secrets/restic"))
+        )
+        .password_env(),
+        ("RESTIC_PASSWORD_COMMAND", "pass show restic".to_string())
+    );
+}
+
+/// A `forget --keep-*` policy, scoped per host/tag group so rotating
+/// generations of snapshots expire without pruning other hosts' history.
+#[derive(Debug, Deserialize, Serialize, Default)]
+struct Retention {
+    #[serde(default)]
+    keep_last: Option<u32>,
+    #[serde(default)]
+    keep_daily: Option<u32>,
+    #[serde(default)]
+    keep_weekly: Option<u32>,
+    #[serde(default)]
+    keep_monthly: Option<u32>,
+    #[serde(default)]
+    keep_yearly: Option<u32>,
+    #[serde(default)]
+    keep_within: Option<String>,
+}
+
+impl Retention {
+    fn forget_args(&self) -> Vec<String> {
+        let mut args = vec!["--group-by".to_string(), "host,tags".to_string()];
+        if let Some(n) = self.keep_last {
+            args.push("--keep-last".to_string());
+            args.push(n.to_string());
+        }
+        if let Some(n) = self.keep_daily {
+            args.push("--keep-daily".to_string());
+            args.push(n.to_string());
+        }
+        if let Some(n) = self.keep_weekly {
+            args.push("--keep-weekly".to_string());
+            args.push(n.to_string());
+        }
+        if let Some(n) = self.keep_monthly {
+            args.push("--keep-monthly".to_string());
+            args.push(n.to_string());
+        }
+        if let Some(n) = self.keep_yearly {
+            args.push("--keep-yearly".to_string());
+            args.push(n.to_string());
+        }
+        if let Some(within) = &self.keep_within {
+            args.push("--keep-within".to_string());
+            args.push(within.clone());
+        }
+        args
+    }
+}
+
+#[test]
+fn test_retention_forget_args() {
+    let retention = Retention {
+        keep_last: Some(5),
+        keep_daily: Some(7),
+        keep_weekly: None,
+        keep_monthly: Some(6),
+        keep_yearly: None,
+        keep_within: Some("30d".to_string()),
+    };
+    let expected: Vec<String> = vec![
+        "--group-by",
+        "host,tags",
+        "--keep-last",
+        "5",
+        "--keep-daily",
+        "7",
+        "--keep-monthly",
+        "6",
+        "--keep-within",
+        "30d",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect();
+    assert_eq!(retention.forget_args(), expected);
+}
+
+/// A storage backend that `restic()` can target: it knows its own repository
+/// URL and whatever environment variables restic needs to authenticate
+/// against it, so new backends never have to touch the command-building code.
+trait Backend {
+    fn repo_url(&self) -> String;
+    fn env(&self) -> Result<Vec<(String, String)>, anyhow::Error>;
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 enum Repository {
     S3(S3Info),
+    Azure(AzureInfo),
+    Gcs(GcsInfo),
+    B2(B2Info),
     Local(LocalPath),
 }
 
 impl Repository {
-    fn path(&self) -> String {
+    fn backend(&self) -> &dyn Backend {
         match self {
-            Repository::Local(path) => path.path.display().to_string(),
-            Repository::S3(s3) => s3.clone().url(),
+            Repository::Local(path) => path,
+            Repository::S3(s3) => s3,
+            Repository::Azure(azure) => azure,
+            Repository::Gcs(gcs) => gcs,
+            Repository::B2(b2) => b2,
         }
     }
+
+    fn path(&self) -> String {
+        self.backend().repo_url()
+    }
+
+    fn env(&self) -> Result<Vec<(String, String)>, anyhow::Error> {
+        self.backend().env()
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -46,12 +199,24 @@ struct LocalPath {
     path: PathBuf,
 }
 
+impl Backend for LocalPath {
+    fn repo_url(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn env(&self) -> Result<Vec<(String, String)>, anyhow::Error> {
+        Ok(vec![])
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 struct S3Info {
     bucket: String,
     endpoint: Option<String>,
-    access_key_id: String,
-    secret_access_key: String,
+    #[serde(default)]
+    access_key_id: Option<String>,
+    #[serde(default)]
+    secret_access_key: Option<String>,
     region: String,
 }
 
@@ -60,8 +225,8 @@ impl Default for S3Info {
         Self {
             bucket: "my_bucket".into(),
             endpoint: Some("https://my-s3-endpoint.net".into()),
-            access_key_id: "access_key_id".into(),
-            secret_access_key: "secret_access_key".into(),
+            access_key_id: Some("access_key_id".into()),
+            secret_access_key: Some("secret_access_key".into()),
             region: "us-east-1".into(),
         }
     }
@@ -77,25 +242,384 @@ impl S3Info {
     }
 }
 
+impl Backend for S3Info {
+    fn repo_url(&self) -> String {
+        self.clone().url()
+    }
+
+    fn env(&self) -> Result<Vec<(String, String)>, anyhow::Error> {
+        let creds = match (&self.access_key_id, &self.secret_access_key) {
+            (Some(access_key_id), Some(secret_access_key)) => AwsCredentials {
+                access_key_id: access_key_id.clone(),
+                secret_access_key: secret_access_key.clone(),
+                session_token: None,
+            },
+            _ => resolve_aws_credentials().ok_or_else(|| {
+                format_err!(
+                    "no AWS credentials found in config, the environment, ~/.aws/credentials, \
+                     or instance metadata"
+                )
+            })?,
+        };
+
+        let mut env = vec![
+            ("AWS_DEFAULT_REGION".to_string(), self.region.clone()),
+            ("AWS_ACCESS_KEY_ID".to_string(), creds.access_key_id),
+            (
+                "AWS_SECRET_ACCESS_KEY".to_string(),
+                creds.secret_access_key,
+            ),
+        ];
+        if let Some(session_token) = creds.session_token {
+            env.push(("AWS_SESSION_TOKEN".to_string(), session_token));
+        }
+        Ok(env)
+    }
+}
+
 #[test]
 fn test_s3_url() {
     let s3 = S3Info {
         bucket: "foo".to_string(),
-        access_key_id: "baz".to_string(),
-        secret_access_key: "bar".to_string(),
+        access_key_id: Some("baz".to_string()),
+        secret_access_key: Some("bar".to_string()),
         endpoint: None,
+        region: "us-east-1".to_string(),
     };
     assert_eq!("s3:s3.amazonaws.com/foo", s3.url());
 }
 
+/// Resolved AWS credentials, found by `resolve_aws_credentials()` when an
+/// `S3Info` doesn't carry `access_key_id`/`secret_access_key` directly.
+#[derive(Debug, Clone)]
+struct AwsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+/// Walks the standard AWS credential chain: environment variables, the
+/// shared credentials file (`~/.aws/credentials`), then EC2/ECS instance
+/// metadata. The first provider that yields a complete set wins.
+fn resolve_aws_credentials() -> Option<AwsCredentials> {
+    aws_credentials_from_env()
+        .or_else(aws_credentials_from_file)
+        .or_else(aws_credentials_from_instance_metadata)
+}
+
+fn aws_credentials_from_env() -> Option<AwsCredentials> {
+    let access_key_id = std::env::var("AWS_ACCESS_KEY_ID").ok()?;
+    let secret_access_key = std::env::var("AWS_SECRET_ACCESS_KEY").ok()?;
+    let session_token = std::env::var("AWS_SESSION_TOKEN").ok();
+    Some(AwsCredentials {
+        access_key_id,
+        secret_access_key,
+        session_token,
+    })
+}
+
+#[test]
+fn test_aws_credentials_from_env() {
+    std::env::remove_var("AWS_ACCESS_KEY_ID");
+    std::env::remove_var("AWS_SECRET_ACCESS_KEY");
+    std::env::remove_var("AWS_SESSION_TOKEN");
+    assert!(aws_credentials_from_env().is_none());
+
+    std::env::set_var("AWS_ACCESS_KEY_ID", "env_key");
+    std::env::set_var("AWS_SECRET_ACCESS_KEY", "env_secret");
+    std::env::set_var("AWS_SESSION_TOKEN", "env_token");
+    let creds = aws_credentials_from_env().unwrap();
+    assert_eq!(creds.access_key_id, "env_key");
+    assert_eq!(creds.secret_access_key, "env_secret");
+    assert_eq!(creds.session_token, Some("env_token".to_string()));
+
+    std::env::remove_var("AWS_ACCESS_KEY_ID");
+    std::env::remove_var("AWS_SECRET_ACCESS_KEY");
+    std::env::remove_var("AWS_SESSION_TOKEN");
+}
+
+fn aws_credentials_from_file() -> Option<AwsCredentials> {
+    let basedirs = directories::BaseDirs::new()?;
+    let path = basedirs.home_dir().join(".aws").join("credentials");
+    let contents = fs::read_to_string(path).ok()?;
+    let profile = std::env::var("AWS_PROFILE").unwrap_or_else(|_| "default".to_string());
+    let profiles = parse_ini(&contents);
+    let section = profiles.get(&profile)?;
+    Some(AwsCredentials {
+        access_key_id: section.get("aws_access_key_id")?.clone(),
+        secret_access_key: section.get("aws_secret_access_key")?.clone(),
+        session_token: section.get("aws_session_token").cloned(),
+    })
+}
+
+/// Minimal parser for the INI format used by `~/.aws/credentials`: `[profile]`
+/// section headers followed by `key = value` lines.
+fn parse_ini(contents: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut section = String::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            section = line[1..line.len() - 1].trim().to_string();
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            sections
+                .entry(section.clone())
+                .or_default()
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    sections
+}
+
+#[test]
+fn test_parse_ini() {
+    let ini = "\
+[default]
+aws_access_key_id = default_key
+aws_secret_access_key = default_secret
+
+# a comment
+[work]
+aws_access_key_id = work_key
+aws_secret_access_key = work_secret
+aws_session_token = work_token
+";
+    let sections = parse_ini(ini);
+    assert_eq!(
+        sections["default"].get("aws_access_key_id"),
+        Some(&"default_key".to_string())
+    );
+    assert_eq!(
+        sections["work"].get("aws_session_token"),
+        Some(&"work_token".to_string())
+    );
+    assert_eq!(sections["default"].get("aws_session_token"), None);
+}
+
+#[derive(Debug, Deserialize)]
+struct InstanceMetadataCredentials {
+    #[serde(rename = "AccessKeyId")]
+    access_key_id: String,
+    #[serde(rename = "SecretAccessKey")]
+    secret_access_key: String,
+    #[serde(rename = "Token")]
+    token: String,
+    #[serde(rename = "Expiration")]
+    expiration: String,
+}
+
+type CachedInstanceCredentials = Mutex<Option<(AwsCredentials, DateTime<Utc>)>>;
+
+fn instance_metadata_cache() -> &'static CachedInstanceCredentials {
+    static CACHE: OnceLock<CachedInstanceCredentials> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(None))
+}
+
+fn aws_credentials_from_instance_metadata() -> Option<AwsCredentials> {
+    let cache = instance_metadata_cache();
+    let fresh = cache
+        .lock()
+        .unwrap()
+        .clone()
+        .filter(|(_, expiration)| *expiration > Utc::now());
+    if let Some((creds, _)) = fresh {
+        return Some(creds);
+    }
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_millis(500))
+        .build();
+    let base = "http://169.254.169.254/latest/meta-data/iam/security-credentials/";
+    let role = agent.get(base).call().ok()?.into_string().ok()?;
+    let role = role.trim();
+    let body: InstanceMetadataCredentials = agent
+        .get(&format!("{}{}", base, role))
+        .call()
+        .ok()?
+        .into_json()
+        .ok()?;
+    let expiration = DateTime::parse_from_rfc3339(&body.expiration)
+        .ok()?
+        .with_timezone(&Utc);
+
+    let creds = AwsCredentials {
+        access_key_id: body.access_key_id,
+        secret_access_key: body.secret_access_key,
+        session_token: Some(body.token),
+    };
+    *cache.lock().unwrap() = Some((creds.clone(), expiration));
+    Some(creds)
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct AzureInfo {
+    container: String,
+    path: PathBuf,
+    account_name: String,
+    account_key: String,
+}
+
+impl AzureInfo {
+    fn url(&self) -> String {
+        format!("azure:{}:{}", self.container, self.path.display())
+    }
+}
+
+impl Backend for AzureInfo {
+    fn repo_url(&self) -> String {
+        self.url()
+    }
+
+    fn env(&self) -> Result<Vec<(String, String)>, anyhow::Error> {
+        Ok(vec![
+            ("AZURE_ACCOUNT_NAME".to_string(), self.account_name.clone()),
+            ("AZURE_ACCOUNT_KEY".to_string(), self.account_key.clone()),
+        ])
+    }
+}
+
+#[test]
+fn test_azure_url() {
+    let azure = AzureInfo {
+        container: "foo".to_string(),
+        path: PathBuf::from("/bar"),
+        account_name: "baz".to_string(),
+        account_key: "qux".to_string(),
+    };
+    assert_eq!("azure:foo:/bar", azure.url());
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct GcsInfo {
+    bucket: String,
+    path: PathBuf,
+    project_id: String,
+    credentials_file: PathBuf,
+}
+
+impl GcsInfo {
+    fn url(&self) -> String {
+        format!("gs:{}:{}", self.bucket, self.path.display())
+    }
+}
+
+impl Backend for GcsInfo {
+    fn repo_url(&self) -> String {
+        self.url()
+    }
+
+    fn env(&self) -> Result<Vec<(String, String)>, anyhow::Error> {
+        Ok(vec![
+            ("GOOGLE_PROJECT_ID".to_string(), self.project_id.clone()),
+            (
+                "GOOGLE_APPLICATION_CREDENTIALS".to_string(),
+                self.credentials_file.display().to_string(),
+            ),
+        ])
+    }
+}
+
+#[test]
+fn test_gcs_url() {
+    let gcs = GcsInfo {
+        bucket: "foo".to_string(),
+        path: PathBuf::from("/bar"),
+        project_id: "baz".to_string(),
+        credentials_file: PathBuf::from("/creds.json"),
+    };
+    assert_eq!("gs:foo:/bar", gcs.url());
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct B2Info {
+    bucket: String,
+    path: PathBuf,
+    account_id: String,
+    account_key: String,
+}
+
+impl B2Info {
+    fn url(&self) -> String {
+        format!("b2:{}:{}", self.bucket, self.path.display())
+    }
+}
+
+impl Backend for B2Info {
+    fn repo_url(&self) -> String {
+        self.url()
+    }
+
+    fn env(&self) -> Result<Vec<(String, String)>, anyhow::Error> {
+        Ok(vec![
+            ("B2_ACCOUNT_ID".to_string(), self.account_id.clone()),
+            ("B2_ACCOUNT_KEY".to_string(), self.account_key.clone()),
+        ])
+    }
+}
+
+#[test]
+fn test_b2_url() {
+    let b2 = B2Info {
+        bucket: "foo".to_string(),
+        path: PathBuf::from("/bar"),
+        account_id: "baz".to_string(),
+        account_key: "qux".to_string(),
+    };
+    assert_eq!("b2:foo:/bar", b2.url());
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct Secretz {
     path: PathBuf,
 }
 
+/// Tracks every path `Secretz::adopt` has pulled into the pack directory, so
+/// `Secretz::restore` can recreate the symlinks back to a fresh home directory.
+#[derive(Debug, Deserialize, Serialize, Default)]
+struct SecretzManifest {
+    #[serde(default)]
+    paths: Vec<PathBuf>,
+}
+
+impl SecretzManifest {
+    fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        match fs::read_to_string(path) {
+            Ok(toml) => Ok(toml::from_str(&toml)?),
+            Err(_) => Ok(Default::default()),
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<(), anyhow::Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
 impl Secretz {
+    fn user_dir(&self) -> PathBuf {
+        self.path.join(&whoami::username())
+    }
+
     fn pack_dir(&self) -> PathBuf {
-        self.path.join(&whoami::username()).join("pack")
+        self.user_dir().join("pack")
+    }
+
+    fn manifest_path(&self) -> PathBuf {
+        self.user_dir().join("manifest.toml")
+    }
+
+    fn home_dir() -> Result<PathBuf, anyhow::Error> {
+        let basedirs = directories::BaseDirs::new()
+            .ok_or_else(|| format_err!("could not determine home directory"))?;
+        Ok(basedirs.home_dir().to_path_buf())
     }
 
     fn adopt(&self, path: PathBuf) -> Result<(), anyhow::Error> {
@@ -105,25 +629,164 @@ impl Secretz {
         if fs::symlink_metadata(&path)?.file_type().is_symlink() {
             return Err(format_err!("should not be a symlink"));
         }
-        if let Some(basedirs) = directories::BaseDirs::new() {
-            if let Some(relpath) = diff_paths(&path, &basedirs.home_dir()) {
-                if let Some(parent) = &relpath.parent() {
-                    let target_dir = self.pack_dir().join(&parent);
-                    fs::create_dir_all(&target_dir)?;
-                    fs::copy(&path, &self.pack_dir().join(&relpath))?;
-                    fs::remove_file(&path)?
+
+        let abspath = if path.is_absolute() {
+            path.clone()
+        } else {
+            std::env::current_dir()?.join(&path)
+        };
+        let relpath = diff_paths(&abspath, Self::home_dir()?)
+            .filter(|relpath| !relpath.starts_with(".."))
+            .ok_or_else(|| format_err!("{} is not under the home directory", path.display()))?;
+        let target = self.pack_dir().join(&relpath);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&path, &target)?;
+        fs::remove_file(&path)?;
+        std::os::unix::fs::symlink(&target, &path)?;
+
+        let manifest_path = self.manifest_path();
+        let mut manifest = SecretzManifest::load(&manifest_path)?;
+        if !manifest.paths.contains(&relpath) {
+            manifest.paths.push(relpath);
+        }
+        manifest.save(&manifest_path)?;
+
+        Ok(())
+    }
+
+    fn restore(&self, force: bool) -> Result<(), anyhow::Error> {
+        let manifest = SecretzManifest::load(&self.manifest_path())?;
+        let home_dir = Self::home_dir()?;
+        for relpath in &manifest.paths {
+            let target = self.pack_dir().join(relpath);
+            let link = home_dir.join(relpath);
+            if let Some(parent) = link.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            if let Ok(metadata) = fs::symlink_metadata(&link) {
+                if !metadata.file_type().is_symlink() {
+                    return Err(format_err!(
+                        "{} already exists and is not a symlink, refusing to overwrite",
+                        link.display()
+                    ));
+                }
+                if !force {
+                    continue;
                 }
+                fs::remove_file(&link)?;
             }
+
+            std::os::unix::fs::symlink(&target, &link)?;
         }
 
         Ok(())
     }
 }
 
+#[test]
+fn test_secretz_adopt_and_restore_round_trip() {
+    let home = std::env::temp_dir().join(format!("wk-test-home-{}", std::process::id()));
+    fs::create_dir_all(&home).unwrap();
+    std::env::set_var("HOME", &home);
+
+    let secretz = Secretz {
+        path: home.join("secretz-store"),
+    };
+    let original = home.join(".testsecret");
+    fs::write(&original, "hunter2").unwrap();
+
+    secretz.adopt(original.clone()).unwrap();
+    assert!(fs::symlink_metadata(&original)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(fs::read_to_string(&original).unwrap(), "hunter2");
+
+    fs::remove_file(&original).unwrap();
+    secretz.restore(false).unwrap();
+    assert!(fs::symlink_metadata(&original)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(fs::read_to_string(&original).unwrap(), "hunter2");
+
+    fs::remove_dir_all(&home).ok();
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct Config {
     secretz: Secretz,
     backup: Backup,
+    repositories: Repositories,
+}
+
+/// Named storage backends, e.g. `[repositories.offsite]` and
+/// `[repositories.local]`, with `default` picking the one `backup`
+/// subcommands target when `--repo` isn't given.
+#[derive(Debug, Deserialize, Serialize)]
+struct Repositories {
+    default: String,
+    #[serde(flatten)]
+    named: HashMap<String, Repository>,
+}
+
+impl Repositories {
+    fn resolve(&self, name: &Option<String>) -> Result<&Repository, anyhow::Error> {
+        let name = match name {
+            Some(name) if !name.is_empty() && name != "::" => name.as_str(),
+            _ => self.default.as_str(),
+        };
+        self.named
+            .get(name)
+            .ok_or_else(|| format_err!("no such repository: {}", name))
+    }
+}
+
+#[test]
+fn test_repositories_resolve() {
+    let repositories = Repositories {
+        default: "local".to_string(),
+        named: [
+            (
+                "local".to_string(),
+                Repository::Local(LocalPath {
+                    path: PathBuf::from("/mnt/local"),
+                }),
+            ),
+            (
+                "offsite".to_string(),
+                Repository::Local(LocalPath {
+                    path: PathBuf::from("/mnt/offsite"),
+                }),
+            ),
+        ]
+        .into_iter()
+        .collect(),
+    };
+
+    assert_eq!(repositories.resolve(&None).unwrap().path(), "/mnt/local");
+    assert_eq!(
+        repositories.resolve(&Some("".to_string())).unwrap().path(),
+        "/mnt/local"
+    );
+    assert_eq!(
+        repositories
+            .resolve(&Some("::".to_string()))
+            .unwrap()
+            .path(),
+        "/mnt/local"
+    );
+    assert_eq!(
+        repositories
+            .resolve(&Some("offsite".to_string()))
+            .unwrap()
+            .path(),
+        "/mnt/offsite"
+    );
+    assert!(repositories.resolve(&Some("nope".to_string())).is_err());
 }
 
 impl Config {
@@ -160,24 +823,38 @@ impl Default for Config {
         Self {
             backup: Backup {
                 password: "very_secure_password".to_string(),
+                password_command: None,
+                password_file: None,
                 excludes: vec!["target".to_string()],
                 targets: vec!["/mnt/codez".to_string(), "/mnt/secretz".to_string()],
-                repository: Repository::Local(LocalPath {
-                    path: Path::new("/mnt/backupz/wk").to_path_buf(),
-                }),
+                retention: None,
             },
             secretz: Secretz {
                 path: Path::new("/mnt/secretz").to_path_buf(),
             },
+            repositories: Repositories {
+                default: "local".to_string(),
+                named: [(
+                    "local".to_string(),
+                    Repository::Local(LocalPath {
+                        path: Path::new("/mnt/backupz/wk").to_path_buf(),
+                    }),
+                )]
+                .into_iter()
+                .collect(),
+            },
         }
     }
 }
 
 #[derive(StructOpt, Debug)]
 enum Cli {
-    #[structopt(name = "adopt")]
-    /// adopt a file into secretz
-    Adopt { file: PathBuf },
+    #[structopt(name = "secretz")]
+    /// manage secretz-tracked dotfiles
+    Secretz {
+        #[structopt(subcommand)]
+        secretz: SecretzSubcommands,
+    },
 
     #[structopt(name = "config")]
     /// manage configuration
@@ -188,11 +865,30 @@ enum Cli {
     #[structopt(name = "backup")]
     /// start a backup
     Backup {
+        /// the named repository to target, defaults to `repositories.default`
+        #[structopt(long = "repo")]
+        repo: Option<String>,
+
         #[structopt(subcommand)]
         backup: BackupSubcommands,
     },
 }
 
+#[derive(StructOpt, Debug)]
+enum SecretzSubcommands {
+    #[structopt(name = "adopt")]
+    /// adopt a file into secretz
+    Adopt { file: PathBuf },
+
+    #[structopt(name = "restore")]
+    /// recreate symlinks for every adopted file on a fresh machine
+    Restore {
+        /// overwrite existing symlinks that point elsewhere
+        #[structopt(short = "f", long = "force")]
+        force: bool,
+    },
+}
+
 #[derive(StructOpt, Debug)]
 enum ConfigSubcommands {
     #[structopt(name = "init")]
@@ -239,38 +935,58 @@ enum BackupSubcommands {
         /// the backup snapshot id, "latest" is accepted
         snapshot_id: String,
     },
+
+    #[structopt(name = "forget")]
+    /// expire old snapshots according to the configured retention policy
+    Forget {
+        /// show what would be removed without deleting anything
+        #[structopt(long = "dry-run")]
+        dry_run: bool,
+    },
 }
 
-fn restic(backup: &Backup, main_cmd: &str, extra_args: Vec<String>) -> duct::Expression {
-    let path = &backup.repository.path();
+fn restic(
+    backup: &Backup,
+    repository: &Repository,
+    main_cmd: &str,
+    extra_args: Vec<String>,
+) -> Result<duct::Expression, anyhow::Error> {
+    let path = &repository.path();
     let mut args = vec![main_cmd];
     args.extend(extra_args.iter().map(|s| s.as_str()).collect::<Vec<&str>>());
+    let (password_key, password_value) = backup.password_env();
     let mut c = cmd("restic", &args)
         .env("RESTIC_REPOSITORY", path)
-        .env("RESTIC_PASSWORD", &backup.password);
-    if let Repository::S3(s3) = &backup.repository {
-        c = c
-            .env("AWS_DEFAULT_REGION", &s3.region)
-            .env("AWS_ACCESS_KEY_ID", &s3.access_key_id)
-            .env("AWS_SECRET_ACCESS_KEY", &s3.secret_access_key);
+        .env(password_key, password_value);
+    for (key, value) in repository.env()? {
+        c = c.env(key, value);
     }
-    c
+    Ok(c)
 }
 
 fn main() -> Result<(), anyhow::Error> {
     match Cli::from_args() {
-        Cli::Adopt { file } => {
-            let config = Config::load()?;
-            config.secretz.adopt(file)?;
-            println!("file adopted, now start a new shell");
-        }
-        Cli::Backup { backup } => match backup {
+        Cli::Secretz { secretz } => match secretz {
+            SecretzSubcommands::Adopt { file } => {
+                let config = Config::load()?;
+                config.secretz.adopt(file)?;
+                println!("file adopted, now start a new shell");
+            }
+            SecretzSubcommands::Restore { force } => {
+                let config = Config::load()?;
+                config.secretz.restore(force)?;
+                println!("secretz restored");
+            }
+        },
+        Cli::Backup { repo, backup } => match backup {
             BackupSubcommands::Init { force: _ } => {
                 let config = Config::load()?;
-                restic(&config.backup, "init", vec![]).run()?;
+                let repository = config.repositories.resolve(&repo)?;
+                restic(&config.backup, repository, "init", vec![])?.run()?;
             }
             BackupSubcommands::Run => {
                 let config = Config::load()?;
+                let repository = config.repositories.resolve(&repo)?;
                 let mut extra_args = vec![];
                 for exclude in &config.backup.excludes {
                     extra_args.push(format!("--exclude={}", exclude));
@@ -278,11 +994,12 @@ fn main() -> Result<(), anyhow::Error> {
                 for target in &config.backup.targets {
                     extra_args.push(target.to_string());
                 }
-                restic(&config.backup, "backup", extra_args).run()?;
+                restic(&config.backup, repository, "backup", extra_args)?.run()?;
             }
             BackupSubcommands::Snapshots => {
                 let config = Config::load()?;
-                restic(&config.backup, "snapshots", vec![]).run()?;
+                let repository = config.repositories.resolve(&repo)?;
+                restic(&config.backup, repository, "snapshots", vec![])?.run()?;
             }
             BackupSubcommands::Restore {
                 host,
@@ -295,8 +1012,10 @@ fn main() -> Result<(), anyhow::Error> {
                 } else {
                     Config::load()?
                 };
+                let repository = config.repositories.resolve(&repo)?;
                 restic(
                     &config.backup,
+                    repository,
                     "restore",
                     vec![
                         "-H".to_string(),
@@ -305,9 +1024,26 @@ fn main() -> Result<(), anyhow::Error> {
                         target,
                         snapshot_id,
                     ],
-                )
+                )?
                 .run()?;
             }
+            BackupSubcommands::Forget { dry_run } => {
+                let config = Config::load()?;
+                let repository = config.repositories.resolve(&repo)?;
+                let retention = config.backup.retention.as_ref().ok_or_else(|| {
+                    format_err!(
+                        "no retention policy configured, add a [backup.retention] section with \
+                         at least one keep_* option"
+                    )
+                })?;
+                let mut extra_args = retention.forget_args();
+                if dry_run {
+                    extra_args.push("--dry-run".to_string());
+                } else {
+                    extra_args.push("--prune".to_string());
+                }
+                restic(&config.backup, repository, "forget", extra_args)?.run()?;
+            }
         },
         Cli::Config { config } => match config {
             ConfigSubcommands::Init {
@@ -322,7 +1058,11 @@ fn main() -> Result<(), anyhow::Error> {
                 }
                 let mut config: Config = Default::default();
                 if remote_storage {
-                    config.backup.repository = Repository::S3(S3Info::default());
+                    config
+                        .repositories
+                        .named
+                        .insert("offsite".to_string(), Repository::S3(S3Info::default()));
+                    config.repositories.default = "offsite".to_string();
                 }
                 config.save()?;
                 eprintln!("successfully written new config to {}", &path.display());